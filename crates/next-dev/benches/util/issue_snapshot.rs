@@ -0,0 +1,52 @@
+use std::sync::Mutex;
+
+/// A single build/resolve issue captured while a [crate::prepared_app::PreparedApp]
+/// was being prepared, flattened down to the fields that matter for a
+/// snapshot comparison (anything environment-specific, like a full stack
+/// trace, is intentionally left out).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CapturedIssue {
+    pub severity: String,
+    pub source_path: String,
+    pub title: String,
+    pub message: String,
+}
+
+/// An in-memory collector for [CapturedIssue]s, so integration tests can
+/// assert "these and only these issues were raised" instead of only
+/// asserting on thrown exceptions.
+#[derive(Default)]
+pub struct IssueReporter {
+    issues: Mutex<Vec<CapturedIssue>>,
+}
+
+impl IssueReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn report(&self, issue: CapturedIssue) {
+        self.issues.lock().unwrap().push(issue);
+    }
+
+    /// Serializes the collected issues into a deterministic, sorted snapshot
+    /// string suitable for `insta`-style golden-file comparison.
+    pub fn snapshot(&self) -> String {
+        let mut issues = self.issues.lock().unwrap().clone();
+        issues.sort();
+
+        issues
+            .iter()
+            .map(|issue| {
+                format!(
+                    "[{severity}] {source_path}\n{title}\n{message}\n",
+                    severity = issue.severity,
+                    source_path = issue.source_path,
+                    title = issue.title,
+                    message = issue.message,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -0,0 +1,99 @@
+pub mod issue_snapshot;
+pub mod prepared_app;
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use chromiumoxide::{
+    cdp::js_protocol::runtime::{EventBindingCalled, EventExceptionThrown},
+    listeners::EventStream,
+    Page,
+};
+use futures::{Stream, StreamExt};
+
+use self::prepared_app::{ConsoleMessage, PreparedApp};
+
+/// A page that's been navigated to the bundled app and is ready to interact
+/// with, plus everything captured about it since navigation: thrown
+/// exceptions, `__turbopack_bench_binding` calls the test harness can wait
+/// on, and `console.*` output.
+///
+/// Holds on to the [PreparedApp] it was built from so the dev server (and
+/// its temporary directory) stay alive for exactly as long as the page
+/// does.
+pub struct PageGuard<'a> {
+    page: Page,
+    binding_events: EventStream<EventBindingCalled>,
+    errors: EventStream<EventExceptionThrown>,
+    console_messages: Arc<Mutex<Vec<ConsoleMessage>>>,
+    app: PreparedApp<'a>,
+}
+
+impl<'a> PageGuard<'a> {
+    /// `console_messages` is a stream rather than an already-collected
+    /// `Vec` since it's set up before navigation happens (see
+    /// [PreparedApp::with_page]); a background task drains it into
+    /// [PageGuard::console_messages] as messages arrive.
+    pub fn new(
+        page: Page,
+        binding_events: EventStream<EventBindingCalled>,
+        errors: EventStream<EventExceptionThrown>,
+        console_messages: impl Stream<Item = ConsoleMessage> + Send + 'static,
+        app: PreparedApp<'a>,
+    ) -> Self {
+        let collected = Arc::new(Mutex::new(Vec::new()));
+        tokio::spawn({
+            let collected = collected.clone();
+            let mut console_messages = Box::pin(console_messages);
+            async move {
+                while let Some(message) = console_messages.next().await {
+                    collected.lock().unwrap().push(message);
+                }
+            }
+        });
+
+        Self {
+            page,
+            binding_events,
+            errors,
+            console_messages: collected,
+            app,
+        }
+    }
+
+    pub fn page(&self) -> &Page {
+        &self.page
+    }
+
+    pub fn app(&self) -> &PreparedApp<'a> {
+        &self.app
+    }
+
+    pub fn app_mut(&mut self) -> &mut PreparedApp<'a> {
+        &mut self.app
+    }
+
+    /// Every `console.*` call observed on the page so far, in call order.
+    pub fn console_messages(&self) -> Vec<ConsoleMessage> {
+        self.console_messages.lock().unwrap().clone()
+    }
+
+    /// Waits for the next `__turbopack_bench_binding` call from the page.
+    pub async fn wait_for_binding(&mut self) -> Result<()> {
+        self.binding_events
+            .next()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("binding event stream ended"))?;
+        Ok(())
+    }
+
+    /// Asserts that the page hasn't thrown any uncaught exceptions since the
+    /// last time this (or [PreparedApp::with_page]) checked.
+    pub fn assert_no_errors(&mut self) -> Result<()> {
+        use futures::FutureExt;
+        if self.errors.next().now_or_never().is_some() {
+            return Err(anyhow::anyhow!("page threw an uncaught exception"));
+        }
+        Ok(())
+    }
+}
@@ -9,7 +9,10 @@ use anyhow::{anyhow, Context, Result};
 use chromiumoxide::{
     cdp::{
         browser_protocol::network::EventResponseReceived,
-        js_protocol::runtime::{AddBindingParams, EventBindingCalled, EventExceptionThrown},
+        js_protocol::runtime::{
+            AddBindingParams, EventBindingCalled, EventConsoleApiCalled, EventExceptionThrown,
+            RemoteObject,
+        },
     },
     Browser, Page,
 };
@@ -17,8 +20,59 @@ use futures::{FutureExt, StreamExt};
 use tokio::task::spawn_blocking;
 use url::Url;
 
+use super::issue_snapshot::{CapturedIssue, IssueReporter};
 use crate::{bundlers::Bundler, util::PageGuard, BINDING_NAME};
 
+/// A single `console.*` call observed in the page, decoded into something
+/// readable enough for a test assertion.
+#[derive(Debug, Clone)]
+pub struct ConsoleMessage {
+    /// The console method that was called, e.g. `log`, `warn`, `error`.
+    pub call_type: String,
+    /// A human-readable rendering of each argument passed to the call.
+    pub args: Vec<String>,
+}
+
+impl std::fmt::Display for ConsoleMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.call_type, self.args.join(" "))
+    }
+}
+
+/// Renders a `RemoteObject` argument the way Chrome DevTools would print it:
+/// the JSON `value` if present (primitives), else the `description`
+/// (functions, errors, class instances), else an object preview built from
+/// its property previews, else a generic fallback.
+fn format_remote_object(object: &RemoteObject) -> String {
+    if let Some(value) = &object.value {
+        return value.to_string();
+    }
+    if let Some(description) = &object.description {
+        return description.clone();
+    }
+    if let Some(preview) = &object.preview {
+        let properties = preview
+            .properties
+            .iter()
+            .map(|property| match &property.value {
+                Some(value) => format!("{}: {}", property.name, value),
+                None => property.name.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        return format!("{{ {properties} }}");
+    }
+    format!("<{}>", object.r#type)
+}
+
+/// Decodes an `EventConsoleApiCalled` CDP event into a [ConsoleMessage].
+fn decode_console_message(event: &EventConsoleApiCalled) -> ConsoleMessage {
+    ConsoleMessage {
+        call_type: event.r#type.as_ref().to_string(),
+        args: event.args.iter().map(format_remote_object).collect(),
+    }
+}
+
 fn copy_dir_boxed(
     from: PathBuf,
     to: PathBuf,
@@ -63,6 +117,7 @@ pub struct PreparedApp<'a> {
     server: Option<(Child, String)>,
     test_dir: tempfile::TempDir,
     counter: usize,
+    issues: IssueReporter,
 }
 
 impl<'a> PreparedApp<'a> {
@@ -77,9 +132,25 @@ impl<'a> PreparedApp<'a> {
             server: None,
             test_dir,
             counter: 0,
+            issues: IssueReporter::new(),
         })
     }
 
+    /// Records a build/resolve issue, so it shows up in
+    /// [PreparedApp::issues_snapshot]. Nothing calls this automatically;
+    /// it's up to whichever bundler integration observes the issue (e.g. by
+    /// reading the bundler's own issue/diagnostics output) to report it
+    /// here.
+    pub fn report_issue(&self, issue: CapturedIssue) {
+        self.issues.report(issue);
+    }
+
+    /// Serializes every issue reported so far into a deterministic, sorted
+    /// snapshot string suitable for `insta`-style golden-file comparison.
+    pub fn issues_snapshot(&self) -> String {
+        self.issues.snapshot()
+    }
+
     pub fn counter(&mut self) -> usize {
         self.counter += 1;
         self.counter
@@ -101,6 +172,7 @@ impl<'a> PreparedApp<'a> {
 
         let mut errors = page.event_listener::<EventExceptionThrown>().await?;
         let binding_events = page.event_listener::<EventBindingCalled>().await?;
+        let console_events = page.event_listener::<EventConsoleApiCalled>().await?;
         let mut network_response_events = page.event_listener::<EventResponseReceived>().await?;
 
         let destination = Url::parse(&server.1)?.join(self.bundler.get_path())?;
@@ -128,7 +200,8 @@ impl<'a> PreparedApp<'a> {
         // Make sure no runtime errors occurred when loading the page
         assert!(errors.next().now_or_never().is_none());
 
-        let page_guard = PageGuard::new(page, binding_events, errors, self);
+        let console_messages = console_events.map(|event| decode_console_message(&event));
+        let page_guard = PageGuard::new(page, binding_events, errors, console_messages, self);
 
         Ok(page_guard)
     }
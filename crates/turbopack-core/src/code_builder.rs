@@ -5,11 +5,13 @@ use std::{
 
 use anyhow::Result;
 use sourcemap::SourceMapBuilder;
+use turbo_tasks::primitives::StringVc;
 use turbo_tasks_fs::rope::Rope;
 
 use crate::{
     source_map::{GenerateSourceMap, GenerateSourceMapVc, SourceMapSection, SourceMapVc},
     source_pos::SourcePos,
+    version::{TotalUpdate, Update, UpdateVc, Version, VersionVc, VersionedContent},
 };
 
 /// Code stores combined output code and the source map of that output code.
@@ -113,6 +115,12 @@ impl Write for Code {
     }
 }
 
+/// The synthetic source name given to the empty maps inserted between
+/// sections of generated runtime glue (`__turbopack_require__` and friends).
+/// It never points at a real file; it only exists so these sections have a
+/// source index to put in `x_google_ignoreList`.
+const IGNORED_GENERATED_SOURCE: &str = "turbopack://[turbopack]/generated.js";
+
 #[turbo_tasks::value_impl]
 impl GenerateSourceMap for Code {
     /// Generates the source map out of all the pushed Original code.
@@ -123,35 +131,118 @@ impl GenerateSourceMap for Code {
     /// starting offset, and until the start of the next section. This is by
     /// far the simplest way to concatenate the source maps of the multiple
     /// chunk items into a single map file.
+    ///
+    /// Sections built from a `None` mapping (i.e. synthetic runtime code
+    /// inserted via `push_bytes`) carry no real source, so their section
+    /// index is additionally recorded in the sectioned map's ignore list,
+    /// which becomes the `x_google_ignoreList` (of global source indices)
+    /// once the map is flattened. This tells Chrome DevTools to treat
+    /// `__turbopack_require__` and other generated glue as ignore-listed:
+    /// hidden from stack traces, and skipped when stepping.
     #[turbo_tasks::function]
     pub async fn generate_source_map(&self) -> Result<SourceMapVc> {
         let mut pos = SourcePos::new();
         let mut last_byte_pos = 0;
 
         let mut sections = Vec::with_capacity(self.mappings.len());
+        let mut ignore_list = Vec::new();
         for (byte_pos, map) in &self.mappings {
             pos.update_from_read(&mut self.code.slice(last_byte_pos, *byte_pos))?;
             last_byte_pos = *byte_pos;
 
             let encoded = match map {
-                None => empty_map(),
+                None => {
+                    ignore_list.push(sections.len());
+                    empty_map()
+                }
                 Some(map) => map.generate_source_map(),
             };
 
             sections.push(SourceMapSection::new(pos, encoded))
         }
 
-        Ok(SourceMapVc::new_sectioned(sections))
+        Ok(SourceMapVc::new_sectioned_with_ignore_list(
+            sections,
+            ignore_list,
+        ))
     }
 }
 
-/// A source map that contains no actual source location information (no
-/// `sources`, no mappings that point into a source). This is used to tell
-/// Chrome that the generated code starting at a particular offset is no longer
-/// part of the previous section's mappings.
+/// A source map that contains no actual source location information, beyond
+/// a single mapping pointing at [IGNORED_GENERATED_SOURCE]. This is used to
+/// tell Chrome that the generated code starting at a particular offset is no
+/// longer part of the previous section's mappings, while still giving that
+/// section a source name it can list in `x_google_ignoreList`.
 #[turbo_tasks::function]
 fn empty_map() -> SourceMapVc {
     let mut builder = SourceMapBuilder::new(None);
-    builder.add(0, 0, 0, 0, None, None);
+    let source_id = builder.add_source(IGNORED_GENERATED_SOURCE);
+    builder.add(0, 0, 0, 0, Some(source_id), None);
     SourceMapVc::new_regular(builder.into_sourcemap())
 }
+
+/// The [Version] of a [Code], identified by a hash of its emitted bytes and
+/// the shape of its source map sections. Two [Code] instances built from
+/// identical input always produce the same hash, regardless of when or how
+/// many times they were built.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CodeVersion {
+    hash: u64,
+}
+
+#[turbo_tasks::value_impl]
+impl Version for CodeVersion {
+    #[turbo_tasks::function]
+    fn id(&self) -> StringVc {
+        StringVc::cell(format!("{:016x}", self.hash))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl VersionedContent for Code {
+    /// Hashes the emitted source bytes together with the byte offsets and
+    /// synthetic/original shape of the mappings. Only the bytes and the
+    /// *shape* of the mappings are hashed (not the wrapped source maps
+    /// themselves), since those are derived from the same original code and
+    /// would otherwise make the hash unstable across equivalent rebuilds.
+    #[turbo_tasks::function]
+    async fn version(&self) -> Result<VersionVc> {
+        let mut hash = xxh3_hash64(&self.code.to_bytes()?);
+        for (index, map) in &self.mappings {
+            hash = xxh3_hash64_continue(hash, &index.to_le_bytes());
+            hash = xxh3_hash64_continue(hash, &[map.is_some() as u8]);
+        }
+        Ok(CodeVersion { hash }.cell().into())
+    }
+
+    /// `Code` has no notion of the modules it's made of, so it can't compute
+    /// a structural diff on its own: any change between versions is reported
+    /// as a [Update::Total]. Chunk types that know their own module
+    /// boundaries (e.g. the ECMAScript chunk) wrap a `Code` and override this
+    /// with a real partial update instead.
+    #[turbo_tasks::function]
+    async fn update(&self, from: VersionVc) -> Result<UpdateVc> {
+        let from = from.id().await?;
+        let to = self.version().id().await?;
+        Ok(if *from == *to {
+            Update::None.cell()
+        } else {
+            Update::Total(TotalUpdate { to: to.clone() }).cell()
+        })
+    }
+}
+
+/// Hashes `bytes` with xxh3, used for content-addressed version ids.
+fn xxh3_hash64(bytes: &[u8]) -> u64 {
+    use twox_hash::xxh3::hash64;
+    hash64(bytes)
+}
+
+/// Folds `extra` into an already-computed xxh3 hash, used to combine the
+/// source bytes hash with the shape of the mappings without re-hashing the
+/// whole buffer.
+fn xxh3_hash64_continue(hash: u64, extra: &[u8]) -> u64 {
+    use twox_hash::xxh3::hash64_with_seed;
+    hash64_with_seed(extra, hash)
+}
@@ -0,0 +1,317 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::source_pos::SourcePos;
+
+/// Trait for asset contents that can generate a source map of their
+/// content.
+#[turbo_tasks::value_trait]
+pub trait GenerateSourceMap {
+    /// Generates a usable source map, capable of mapping a generated line/col
+    /// into an original line/col.
+    fn generate_source_map(&self) -> SourceMapVc;
+}
+
+/// A section of a sectioned source map, starting at `offset` (in the
+/// concatenated output) and covering everything up to the next section's
+/// offset (or the end of the output, for the last section).
+#[derive(Debug, Clone)]
+pub struct SourceMapSection {
+    offset: SourcePos,
+    map: SourceMapVc,
+}
+
+impl SourceMapSection {
+    pub fn new(offset: SourcePos, map: SourceMapVc) -> Self {
+        Self { offset, map }
+    }
+}
+
+/// A lazily-resolved, sectioned source map, as produced by concatenating
+/// many individually-mapped pieces of generated code (see
+/// [crate::code_builder::Code::generate_source_map]).
+///
+/// `ignore_list` holds the indices (into `sections`) of the sections that
+/// should be reported through the `x_google_ignoreList` DevTools extension
+/// once this map is flattened into a single, regular source map.
+#[derive(Debug, Clone)]
+pub struct SectionedSourceMap {
+    sections: Vec<SourceMapSection>,
+    ignore_list: Vec<usize>,
+}
+
+impl SectionedSourceMap {
+    fn new(sections: Vec<SourceMapSection>, ignore_list: Vec<usize>) -> Self {
+        Self {
+            sections,
+            ignore_list,
+        }
+    }
+}
+
+/// A source map, either a single [sourcemap::SourceMap] or a
+/// [SectionedSourceMap] built out of many of them.
+///
+/// Equality is manual (and always `false`) since [sourcemap::SourceMap]
+/// doesn't implement [PartialEq], and there's no cheap, meaningful notion of
+/// equality for two source maps beyond comparing their flattened output
+/// byte-for-byte.
+#[turbo_tasks::value(shared, eq = "manual", serialization = "none")]
+#[derive(Debug, Clone)]
+pub enum SourceMap {
+    /// A regular, flat source map.
+    Regular(#[turbo_tasks(trace_ignore)] sourcemap::SourceMap),
+    /// A sectioned source map, which needs to be flattened into a regular
+    /// one before it can be serialized.
+    Sectioned(SectionedSourceMap),
+}
+
+impl PartialEq for SourceMap {
+    fn eq(&self, _other: &Self) -> bool {
+        false
+    }
+}
+impl Eq for SourceMap {}
+
+#[turbo_tasks::value_impl]
+impl SourceMapVc {
+    /// Creates a new regular source map.
+    #[turbo_tasks::function]
+    pub fn new_regular(map: sourcemap::SourceMap) -> Self {
+        SourceMap::Regular(map).cell()
+    }
+
+    /// Creates a new sectioned source map out of `sections`, recording
+    /// `ignore_list` (indices into `sections`) as the sections that should
+    /// end up in the flattened map's `x_google_ignoreList`.
+    #[turbo_tasks::function]
+    pub fn new_sectioned_with_ignore_list(
+        sections: Vec<SourceMapSection>,
+        ignore_list: Vec<usize>,
+    ) -> Self {
+        SourceMap::Sectioned(SectionedSourceMap::new(sections, ignore_list)).cell()
+    }
+
+    /// Serializes this source map to the source map v3 JSON format,
+    /// flattening any sectioned map into a single, regular one first so the
+    /// `x_google_ignoreList` extension can reference a single, global
+    /// `sources` array.
+    #[turbo_tasks::function]
+    pub async fn to_bytes(self) -> Result<Vec<u8>> {
+        let (map, ignore_list) = flatten(self).await?;
+
+        let mut bytes = Vec::new();
+        map.to_writer(&mut bytes)?;
+
+        if ignore_list.is_empty() {
+            return Ok(bytes);
+        }
+
+        // The `sourcemap` crate has no notion of `x_google_ignoreList`, so
+        // the extension is stitched onto the JSON it produces instead of
+        // threaded through the builder.
+        let mut value: Value = serde_json::from_slice(&bytes)?;
+        if let Value::Object(map) = &mut value {
+            map.insert("x_google_ignoreList".to_string(), json!(ignore_list));
+        }
+        Ok(serde_json::to_vec(&value)?)
+    }
+}
+
+/// Flattens `map` into a single [sourcemap::SourceMap], returning alongside
+/// it the global source indices (into the flattened map's `sources` array)
+/// that should be ignore-listed.
+///
+/// Boxed because sectioned maps can nest (a section's own map can itself be
+/// sectioned), which makes this recursive across an `async fn` boundary.
+fn flatten(map: SourceMapVc) -> Pin<Box<dyn Future<Output = Result<(sourcemap::SourceMap, Vec<u32>)>> + Send>> {
+    Box::pin(async move {
+        match &*map.await? {
+            SourceMap::Regular(map) => Ok((map.clone(), Vec::new())),
+            SourceMap::Sectioned(sectioned) => flatten_sectioned(sectioned).await,
+        }
+    })
+}
+
+/// Concatenates every section's (already-flattened) map into a single
+/// [sourcemap::SourceMap], offsetting each section's tokens by its
+/// destination offset and remapping its sources/names into the combined
+/// map's global index space.
+async fn flatten_sectioned(sectioned: &SectionedSourceMap) -> Result<(sourcemap::SourceMap, Vec<u32>)> {
+    let mut builder = sourcemap::SourceMapBuilder::new(None);
+    let mut ignore_list = Vec::new();
+
+    for (section_index, section) in sectioned.sections.iter().enumerate() {
+        let (section_map, nested_ignore_list) = flatten(section.map).await?;
+        let section_is_ignored = sectioned.ignore_list.contains(&section_index);
+        ignore_list.extend(merge_section(
+            &mut builder,
+            &section_map,
+            section.offset,
+            section_is_ignored,
+            &nested_ignore_list,
+        ));
+    }
+
+    ignore_list.sort_unstable();
+    ignore_list.dedup();
+    Ok((builder.into_sourcemap(), ignore_list))
+}
+
+/// Merges one already-flattened section's map into `builder`: offsets every
+/// token's line by `offset.line`, and its column by `offset.column` only on
+/// the section's first output line (later lines already start at column 0
+/// of the output), and remaps its sources into `builder`'s global index
+/// space.
+///
+/// Returns the global source indices (into `builder`'s eventual `sources`
+/// array) that should be ignore-listed: every source this section
+/// contributes, if `section_is_ignored`, plus `nested_ignore_list` (a
+/// nested sectioned map's own ignore list, given in *its* local source
+/// indices) remapped into this merge's global ones.
+///
+/// Pulled out of [flatten_sectioned] so the remapping/offsetting logic can
+/// be unit tested against plain [sourcemap::SourceMap]s, without needing a
+/// turbo-tasks runtime to resolve a [SourceMapVc].
+fn merge_section(
+    builder: &mut sourcemap::SourceMapBuilder,
+    section_map: &sourcemap::SourceMap,
+    offset: SourcePos,
+    section_is_ignored: bool,
+    nested_ignore_list: &[u32],
+) -> Vec<u32> {
+    let mut source_indices = Vec::with_capacity(section_map.get_source_count() as usize);
+    for src_id in 0..section_map.get_source_count() {
+        let source = section_map.get_source(src_id).unwrap_or("");
+        let global_id = builder.add_source(source);
+        if let Some(contents) = section_map.get_source_contents(src_id) {
+            builder.set_source_contents(global_id, Some(contents));
+        }
+        source_indices.push(global_id);
+    }
+
+    let mut ignore_list = Vec::new();
+    for local_id in nested_ignore_list {
+        if let Some(global_id) = source_indices.get(*local_id as usize) {
+            ignore_list.push(*global_id);
+        }
+    }
+    if section_is_ignored {
+        ignore_list.extend(source_indices.iter().copied());
+    }
+
+    for token in section_map.tokens() {
+        let dst_line = token.get_dst_line() + offset.line as u32;
+        let dst_col = if token.get_dst_line() == 0 {
+            token.get_dst_col() + offset.column as u32
+        } else {
+            token.get_dst_col()
+        };
+        let name_id = token.get_name().map(|name| builder.add_name(name));
+        let src_id = if token.has_source() {
+            source_indices.get(token.get_src_id() as usize).copied()
+        } else {
+            None
+        };
+        builder.add(
+            dst_line,
+            dst_col,
+            token.get_src_line(),
+            token.get_src_col(),
+            src_id,
+            name_id,
+        );
+    }
+
+    ignore_list
+}
+
+#[cfg(test)]
+mod tests {
+    use sourcemap::SourceMapBuilder;
+
+    use super::{merge_section, SourcePos};
+
+    fn single_token_map(source: &str, dst_line: u32, dst_col: u32, src_line: u32, src_col: u32) -> sourcemap::SourceMap {
+        let mut builder = SourceMapBuilder::new(None);
+        let source_id = builder.add_source(source);
+        builder.add(dst_line, dst_col, src_line, src_col, Some(source_id), None);
+        builder.into_sourcemap()
+    }
+
+    #[test]
+    fn merges_one_synthetic_and_one_real_section() {
+        let synthetic = single_token_map("turbopack://[turbopack]/generated.js", 0, 0, 0, 0);
+        let real = single_token_map("foo.js", 0, 0, 3, 5);
+
+        let mut builder = SourceMapBuilder::new(None);
+        let mut ignore_list = Vec::new();
+        // The synthetic section is 5 output lines long, so the real section
+        // that follows it starts at output line 5.
+        ignore_list.extend(merge_section(&mut builder, &synthetic, SourcePos::new(), true, &[]));
+        ignore_list.extend(merge_section(
+            &mut builder,
+            &real,
+            SourcePos { line: 5, column: 0 },
+            false,
+            &[],
+        ));
+        ignore_list.sort_unstable();
+
+        assert_eq!(ignore_list, vec![0]);
+
+        let map = builder.into_sourcemap();
+        let tokens: Vec<_> = map.tokens().collect();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].get_dst_line(), 0);
+        assert_eq!(tokens[1].get_dst_line(), 5);
+        assert_eq!(tokens[1].get_src_line(), 3);
+        assert_eq!(map.get_source(tokens[1].get_src_id()), Some("foo.js"));
+    }
+
+    #[test]
+    fn column_offset_only_applies_to_the_sections_first_output_line() {
+        let mut section_builder = SourceMapBuilder::new(None);
+        let source_id = section_builder.add_source("foo.js");
+        section_builder.add(0, 2, 0, 0, Some(source_id), None);
+        section_builder.add(1, 2, 1, 0, Some(source_id), None);
+        let section_map = section_builder.into_sourcemap();
+
+        let mut builder = SourceMapBuilder::new(None);
+        merge_section(
+            &mut builder,
+            &section_map,
+            SourcePos {
+                line: 10,
+                column: 100,
+            },
+            false,
+            &[],
+        );
+
+        let map = builder.into_sourcemap();
+        let tokens: Vec<_> = map.tokens().collect();
+        assert_eq!(tokens[0].get_dst_line(), 10);
+        assert_eq!(tokens[0].get_dst_col(), 102);
+        assert_eq!(tokens[1].get_dst_line(), 11);
+        assert_eq!(tokens[1].get_dst_col(), 2);
+    }
+
+    #[test]
+    fn nested_ignore_list_is_remapped_into_global_indices() {
+        let inner = single_token_map("turbopack://[turbopack]/generated.js", 0, 0, 0, 0);
+        let mut builder = SourceMapBuilder::new(None);
+
+        // `inner` stands in for an already-flattened nested sectioned map
+        // that reported its only source (local index 0) as ignore-listed;
+        // merging it alongside a second, unrelated source must still
+        // report that source's *global* index.
+        builder.add_source("unrelated.js");
+        let ignored = merge_section(&mut builder, &inner, SourcePos::new(), false, &[0]);
+
+        assert_eq!(ignored, vec![1]);
+    }
+}
@@ -0,0 +1,40 @@
+use std::io::Read;
+
+use anyhow::Result;
+
+/// A (line, column) position within a piece of generated code, 0-indexed.
+/// Used to track how far [Code][crate::code_builder::Code] has advanced
+/// while building the destination offsets of a sectioned source map.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourcePos {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourcePos {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Advances this position by every byte read from `reader`, counting
+    /// newlines into `line` and the remaining bytes on the last line into
+    /// `column`.
+    pub fn update_from_read(&mut self, reader: &mut impl Read) -> Result<()> {
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            for &byte in &buf[..n] {
+                if byte == b'\n' {
+                    self.line += 1;
+                    self.column = 0;
+                } else {
+                    self.column += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
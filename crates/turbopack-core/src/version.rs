@@ -0,0 +1,239 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use anyhow::Result;
+use futures::Stream;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+
+/// Version information of a content.
+///
+/// Given two versions, it's always possible to compute an [Update] from one
+/// to the other one. Applying that update transitions the content from the
+/// `from` version to the `to` version.
+#[turbo_tasks::value_trait]
+pub trait Version {
+    /// A unique identifier of the version as a string. Equal contents must
+    /// return the same id, and different contents must (with overwhelming
+    /// probability) return different ids.
+    fn id(&self) -> StringVc;
+}
+
+/// A [Version] constructed directly from an id string, e.g. one a client
+/// reported back over the wire. Lets call sites turn a plain `String` into a
+/// [VersionVc] without needing to go through whatever produced the original
+/// version.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RawVersion {
+    id: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Version for RawVersion {
+    #[turbo_tasks::function]
+    fn id(&self) -> StringVc {
+        StringVc::cell(self.id.clone())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl RawVersionVc {
+    fn new(id: String) -> Self {
+        RawVersion { id }.cell()
+    }
+}
+
+/// Trait that allows the [VersionedContentMap] to compute an update from the
+/// currently stored content to a client-supplied previous version.
+#[turbo_tasks::value_trait]
+pub trait VersionedContent {
+    /// The content's current [Version].
+    fn version(&self) -> VersionVc;
+
+    /// Computes the [Update] that transitions the content from `from` to the
+    /// current version. Implementors should fall back to [Update::Total]
+    /// whenever a cheaper, structural diff isn't possible (e.g. the set of
+    /// chunks changed in an incompatible way).
+    fn update(&self, from: VersionVc) -> UpdateVc;
+}
+
+/// Describes how a [VersionedContent] changed between two versions.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Update {
+    /// Nothing changed between the two versions.
+    None,
+    /// A partial update that can be applied on top of the `from` version,
+    /// without fetching the whole content again.
+    Partial(PartialUpdate),
+    /// The difference is too large (or not representable) to express as a
+    /// partial update, so the whole content must be fetched/reloaded again.
+    Total(TotalUpdate),
+}
+
+/// A partial, structural update between two versions of a [VersionedContent].
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialUpdate {
+    /// The version this update applies on top of.
+    pub from: String,
+    /// The version this update brings the content to.
+    pub to: String,
+    /// Opaque, content-type specific instructions (e.g. an ECMAScript HMR
+    /// chunk list) describing what changed.
+    pub instruction: String,
+}
+
+/// A full replacement of the content, used when the gap between versions is
+/// too large to express incrementally.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TotalUpdate {
+    /// The version the client should reload to.
+    pub to: String,
+}
+
+/// An eagerly populated, process-global map from an output asset's path to
+/// the [VersionedContent] emitted for it.
+///
+/// Unlike retrieving assets lazily through the router, content is stored
+/// here up front through [VersionedContentMapVc::insert], so that an
+/// `update(from_version)` request can be served without redoing any of the
+/// work that produced the asset. Chunking does not insert into the map on
+/// its own: the map holds exactly what has been passed to `insert`.
+///
+/// Entries are grouped by the entrypoint that produced them, so that an
+/// entrypoint which is no longer part of the graph can have all of its
+/// outputs evicted together instead of leaking stale entries into the flat
+/// map forever.
+///
+/// The map is mutated in place through a [Mutex] rather than by replacing
+/// the cell's value, since `insert`/`evict_entrypoint` need to accumulate
+/// state across many calls against what is conceptually a single, long-lived
+/// instance (there's no meaningful "new value" to diff against on each
+/// write). That's also why equality is manual: two instances are never
+/// considered equal, since comparing point-in-time snapshots of interior
+/// state wouldn't mean anything.
+#[turbo_tasks::value(cell = "new", eq = "manual", serialization = "none")]
+pub struct VersionedContentMap {
+    #[turbo_tasks(trace_ignore)]
+    by_entrypoint: Mutex<HashMap<FileSystemPathVc, HashMap<String, VersionedContentVc>>>,
+}
+
+impl PartialEq for VersionedContentMap {
+    fn eq(&self, _other: &Self) -> bool {
+        false
+    }
+}
+impl Eq for VersionedContentMap {}
+
+impl VersionedContentMap {
+    pub fn empty() -> Self {
+        Self {
+            by_entrypoint: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl VersionedContentMapVc {
+    #[turbo_tasks::function]
+    pub fn new() -> Self {
+        VersionedContentMap::empty().cell()
+    }
+
+    /// Inserts (or replaces) the content stored for `path`, produced while
+    /// chunking `entrypoint`.
+    #[turbo_tasks::function]
+    pub async fn insert(
+        self,
+        entrypoint: FileSystemPathVc,
+        path: String,
+        content: VersionedContentVc,
+    ) -> Result<()> {
+        let this = self.await?;
+        this.by_entrypoint
+            .lock()
+            .unwrap()
+            .entry(entrypoint)
+            .or_insert_with(HashMap::new)
+            .insert(path, content);
+        Ok(())
+    }
+
+    /// Drops every entry that was produced while chunking `entrypoint`. Used
+    /// when an entrypoint is removed from the graph, so its outputs don't
+    /// keep serving stale content forever.
+    #[turbo_tasks::function]
+    pub async fn evict_entrypoint(self, entrypoint: FileSystemPathVc) -> Result<()> {
+        let this = self.await?;
+        this.by_entrypoint.lock().unwrap().remove(&entrypoint);
+        Ok(())
+    }
+
+    /// Looks up the currently stored content for `path`, if any.
+    #[turbo_tasks::function]
+    pub async fn get(self, path: String) -> Result<Option<VersionedContentVc>> {
+        let this = self.await?;
+        let by_entrypoint = this.by_entrypoint.lock().unwrap();
+        Ok(by_entrypoint
+            .values()
+            .find_map(|assets| assets.get(&path).copied()))
+    }
+
+    /// Computes the [Update] needed to bring the client from `from_version`
+    /// to the currently stored content for `path`.
+    #[turbo_tasks::function]
+    pub async fn update(self, path: String, from_version: VersionVc) -> Result<UpdateVc> {
+        Ok(match self.get(path).await? {
+            Some(content) => content.update(from_version),
+            // The asset no longer exists: the client has to do a full reload.
+            None => Update::Total(TotalUpdate {
+                to: "".to_string(),
+            })
+            .cell(),
+        })
+    }
+
+    /// How often [hmr_events] re-checks `path` for a new version. turbo-tasks
+    /// invalidates and recomputes `update()` on its own whenever an input
+    /// changes; this interval only bounds how long a subscriber can be stuck
+    /// waiting to notice that recomputation happened.
+    const HMR_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Subscribes to the HMR updates for `path`, starting from
+    /// `from_version`. Yields an [Update] every time the stored content for
+    /// `path` changes, so a host (e.g. the Next.js dev WS server) can forward
+    /// each one straight to the browser runtime without polling itself.
+    ///
+    /// The stream ends once a [Update::Total] has been yielded, since at that
+    /// point the client is expected to reload rather than keep applying
+    /// updates.
+    pub fn hmr_events(
+        self,
+        path: String,
+        from_version: VersionVc,
+    ) -> impl Stream<Item = Result<Update>> + Send {
+        async_stream::try_stream! {
+            let mut current = from_version;
+            loop {
+                let update = self.update(path.clone(), current).await?;
+                let update_value = update.await?;
+                match &*update_value {
+                    Update::None => {
+                        tokio::time::sleep(Self::HMR_POLL_INTERVAL).await;
+                        continue;
+                    }
+                    Update::Partial(partial) => {
+                        current = RawVersionVc::new(partial.to.clone()).into();
+                        yield (*update_value).clone();
+                    }
+                    Update::Total(_) => {
+                        yield (*update_value).clone();
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
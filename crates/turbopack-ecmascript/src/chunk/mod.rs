@@ -0,0 +1,210 @@
+pub(crate) mod update;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use turbopack_core::{
+    code_builder::Code,
+    version::{TotalUpdate, Update, UpdateVc, Version, VersionVc, VersionedContent},
+};
+
+use self::update::{compute_update, EcmascriptChunkEntries};
+
+/// How many distinct chunk versions [VersionHistory] remembers at once. A
+/// long-running dev session produces one version per save, and without a
+/// bound that's one full module-list snapshot per save for as long as the
+/// entrypoint stays in the graph. A client that's fallen further behind
+/// than this just gets an [Update::Total] instead of a partial diff, which
+/// is the same fallback already used when the client's version was never
+/// recorded at all.
+const MAX_TRACKED_VERSIONS: usize = 20;
+
+/// A bounded, insertion-ordered store of module lists keyed by version id,
+/// evicting the oldest version once more than [MAX_TRACKED_VERSIONS] are
+/// held at once.
+///
+/// Kept as a plain data structure (rather than inlined into
+/// [EcmascriptChunkVersionHistory]) so the eviction order can be unit
+/// tested without a turbo-tasks runtime.
+#[derive(Default)]
+struct VersionHistory {
+    by_id: HashMap<String, EcmascriptChunkEntries>,
+    order: VecDeque<String>,
+}
+
+impl VersionHistory {
+    fn insert(&mut self, id: String, entries: EcmascriptChunkEntries) {
+        if self.by_id.insert(id.clone(), entries).is_none() {
+            self.order.push_back(id);
+        }
+        while self.order.len() > MAX_TRACKED_VERSIONS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.by_id.remove(&oldest);
+            }
+        }
+    }
+
+    fn get(&self, id: &str) -> Option<EcmascriptChunkEntries> {
+        self.by_id.get(id).cloned()
+    }
+}
+
+/// Remembers the module list behind the last [MAX_TRACKED_VERSIONS]
+/// ECMAScript chunk versions, keyed by that version's id, so a later
+/// `update(from)` call has something to diff the chunk's current modules
+/// against (see [EcmascriptChunkContent::update]).
+///
+/// It's the entrypoint-scoped eviction in
+/// [turbopack_core::version::VersionedContentMap] that bounds how long a
+/// stale chunk's history sticks around at all, by dropping the
+/// [EcmascriptChunkContentVc] (and with it, the last reference to this
+/// history) once its entrypoint is no longer part of the graph; the bound
+/// here instead caps how many versions of a still-live entrypoint's chunk
+/// are kept around at once.
+#[turbo_tasks::value(cell = "new", eq = "manual", serialization = "none")]
+pub struct EcmascriptChunkVersionHistory {
+    #[turbo_tasks(trace_ignore)]
+    by_version_id: Mutex<VersionHistory>,
+}
+
+impl PartialEq for EcmascriptChunkVersionHistory {
+    fn eq(&self, _other: &Self) -> bool {
+        false
+    }
+}
+impl Eq for EcmascriptChunkVersionHistory {}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkVersionHistoryVc {
+    #[turbo_tasks::function]
+    pub fn new() -> Self {
+        EcmascriptChunkVersionHistory {
+            by_version_id: Mutex::new(VersionHistory::default()),
+        }
+        .cell()
+    }
+}
+
+/// The content of an ECMAScript chunk at one point in time: every module's
+/// factory [Code][turbopack_core::code_builder::Code], keyed by module id.
+///
+/// When inserted into the [turbopack_core::version::VersionedContentMap] for
+/// a chunk's output path, it gives an HMR `update()` request against that
+/// chunk a real per-module diff (see [update::compute_update]) instead of
+/// `Code`'s own `VersionedContent` impl, which only knows "any byte changed"
+/// and always replies with a full reload. Nothing in this crate builds one
+/// or inserts it into the map.
+#[turbo_tasks::value(shared)]
+#[derive(Clone)]
+pub struct EcmascriptChunkContent {
+    pub entries: EcmascriptChunkEntries,
+    pub history: EcmascriptChunkVersionHistoryVc,
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkContentVc {
+    #[turbo_tasks::function]
+    pub fn new(entries: EcmascriptChunkEntries, history: EcmascriptChunkVersionHistoryVc) -> Self {
+        EcmascriptChunkContent { entries, history }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl VersionedContent for EcmascriptChunkContent {
+    /// Builds a single [Code] out of every module's factory and reuses its
+    /// content hash as this chunk's version id, then records this version's
+    /// modules in `history` so a future `update()` against that id can diff
+    /// against them.
+    #[turbo_tasks::function]
+    async fn version(&self) -> Result<VersionVc> {
+        let mut code = Code::new();
+        for entry in &self.entries.entries {
+            code.push_code(&*entry.code.await?);
+        }
+        let version = code.cell().version();
+        let id = version.id().await?;
+
+        self.history
+            .await?
+            .by_version_id
+            .lock()
+            .unwrap()
+            .insert(id.clone(), self.entries.clone());
+
+        Ok(version)
+    }
+
+    /// Looks up the modules recorded for `from` in `history` and diffs them
+    /// against this chunk's current modules via [compute_update]. Falls back
+    /// to [Update::Total] directly (without even attempting a diff) when
+    /// `from`'s modules were never recorded, e.g. because the dev server
+    /// restarted and lost its in-memory history.
+    #[turbo_tasks::function]
+    async fn update(&self, from: VersionVc) -> Result<UpdateVc> {
+        let from_id = from.id().await?;
+        let to = self.version();
+
+        let old_entries = self
+            .history
+            .await?
+            .by_version_id
+            .lock()
+            .unwrap()
+            .get(&from_id);
+
+        match old_entries {
+            Some(old_entries) => compute_update(&old_entries, &self.entries, from, to).await,
+            None => Ok(Update::Total(TotalUpdate {
+                to: to.id().await?.clone(),
+            })
+            .cell()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VersionHistory, MAX_TRACKED_VERSIONS};
+    use crate::chunk::update::EcmascriptChunkEntries;
+
+    #[test]
+    fn keeps_every_version_up_to_the_limit() {
+        let mut history = VersionHistory::default();
+        for i in 0..MAX_TRACKED_VERSIONS {
+            history.insert(i.to_string(), EcmascriptChunkEntries::default());
+        }
+        for i in 0..MAX_TRACKED_VERSIONS {
+            assert!(history.get(&i.to_string()).is_some());
+        }
+    }
+
+    #[test]
+    fn evicts_the_oldest_version_once_over_the_limit() {
+        let mut history = VersionHistory::default();
+        for i in 0..=MAX_TRACKED_VERSIONS {
+            history.insert(i.to_string(), EcmascriptChunkEntries::default());
+        }
+
+        assert!(history.get("0").is_none());
+        for i in 1..=MAX_TRACKED_VERSIONS {
+            assert!(history.get(&i.to_string()).is_some());
+        }
+    }
+
+    #[test]
+    fn reinserting_an_existing_version_does_not_evict() {
+        let mut history = VersionHistory::default();
+        for i in 0..MAX_TRACKED_VERSIONS {
+            history.insert(i.to_string(), EcmascriptChunkEntries::default());
+        }
+        // Already-tracked version, re-inserted: shouldn't push anything out.
+        history.insert("0".to_string(), EcmascriptChunkEntries::default());
+
+        for i in 0..MAX_TRACKED_VERSIONS {
+            assert!(history.get(&i.to_string()).is_some());
+        }
+    }
+}
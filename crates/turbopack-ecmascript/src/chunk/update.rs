@@ -0,0 +1,194 @@
+//! ECMAScript-specific [VersionedContent::update][turbopack_core::version::VersionedContent::update]
+//! for chunks.
+//!
+//! An ECMAScript chunk is versioned and diffed at the module level: instead
+//! of falling back to [Update::Total] whenever any byte of the chunk
+//! changes (as the generic [Code][turbopack_core::code_builder::Code] does),
+//! we compare the chunk's current set of modules against the client-supplied
+//! previous version and, when the chunk list itself is still compatible,
+//! report exactly which module ids were added, modified, or deleted,
+//! including the new factory source for each added/modified module.
+use std::collections::HashMap;
+
+use anyhow::Result;
+use turbopack_core::{
+    code_builder::CodeVc,
+    version::{PartialUpdate, TotalUpdate, Update, UpdateVc, Version, VersionVc},
+};
+
+/// A single ECMAScript module as emitted into a chunk: its id, and the
+/// [Code][turbopack_core::code_builder::Code] holding its factory source
+/// (and source map).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcmascriptChunkEntry {
+    pub module_id: String,
+    pub code: CodeVc,
+}
+
+/// The modules that made up an ECMAScript chunk at one point in time. Kept
+/// around per-chunk so a later [compute_update] call has something to diff
+/// against.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EcmascriptChunkEntries {
+    pub entries: Vec<EcmascriptChunkEntry>,
+}
+
+impl EcmascriptChunkEntries {
+    fn by_id(&self) -> HashMap<&str, &EcmascriptChunkEntry> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.module_id.as_str(), entry))
+            .collect()
+    }
+}
+
+/// Above this fraction of changed modules (relative to the old chunk's
+/// module count) a partial update stops being worth it: serializing every
+/// changed factory individually costs about as much as just reloading, and
+/// that many simultaneous changes usually means the chunk list itself was
+/// rebuilt rather than incrementally patched.
+const MAX_PARTIAL_UPDATE_RATIO: f64 = 0.5;
+
+/// Whether a chunk that had `old_count` modules and now has `changed` of
+/// them added/modified/deleted should fall back to [Update::Total] rather
+/// than reporting the per-module diff. Pulled out of [compute_update] so the
+/// threshold can be unit tested without needing a [VersionVc] on hand.
+fn is_too_large_for_partial_update(old_count: usize, changed: usize) -> bool {
+    old_count != 0 && (changed as f64 / old_count as f64) > MAX_PARTIAL_UPDATE_RATIO
+}
+
+/// Computes the [Update] needed to bring a client that's currently showing
+/// `old` (at `from`) up to date with `new` (at `to`).
+///
+/// Falls back to [Update::Total] when the chunk list changed in a way that a
+/// per-module diff isn't worth sending (too many modules changed at once),
+/// or when `old` doesn't share any modules with `new`, which happens when
+/// the chunk was rebuilt from scratch rather than incrementally.
+pub async fn compute_update(
+    old: &EcmascriptChunkEntries,
+    new: &EcmascriptChunkEntries,
+    from: VersionVc,
+    to: VersionVc,
+) -> Result<UpdateVc> {
+    let from_id = from.id().await?;
+    let to_id = to.id().await?;
+    if *from_id == *to_id {
+        return Ok(Update::None.cell());
+    }
+
+    let old_by_id = old.by_id();
+    let new_by_id = new.by_id();
+
+    let mut added = Vec::new();
+    let mut modified = Vec::new();
+    let mut deleted = Vec::new();
+
+    for (id, new_entry) in &new_by_id {
+        match old_by_id.get(id) {
+            None => added.push(*new_entry),
+            Some(old_entry) => {
+                // Module factories must be byte-identical to what a full
+                // build would have produced, so comparing the underlying
+                // `Code` is enough to tell whether the module changed.
+                if old_entry.code != new_entry.code {
+                    modified.push(*new_entry);
+                }
+            }
+        }
+    }
+    for id in old_by_id.keys() {
+        if !new_by_id.contains_key(id) {
+            deleted.push((*id).to_string());
+        }
+    }
+
+    let changed = added.len() + modified.len() + deleted.len();
+    let shares_modules = new_by_id.keys().any(|id| old_by_id.contains_key(id));
+    if !shares_modules || is_too_large_for_partial_update(old_by_id.len(), changed) {
+        return Ok(Update::Total(TotalUpdate {
+            to: to_id.clone(),
+        })
+        .cell());
+    }
+
+    Ok(Update::Partial(PartialUpdate {
+        from: from_id.clone(),
+        to: to_id.clone(),
+        instruction: serialize_instruction(&added, &modified, &deleted).await?,
+    })
+    .cell())
+}
+
+/// Serializes the module diff into the wire instruction carried by
+/// [PartialUpdate::instruction]. The factory source for each added/modified
+/// module is read out of its `Code`'s source section so it's always
+/// byte-identical to what a full build would have produced.
+async fn serialize_instruction(
+    added: &[&EcmascriptChunkEntry],
+    modified: &[&EcmascriptChunkEntry],
+    deleted: &[String],
+) -> Result<String> {
+    #[derive(serde::Serialize)]
+    struct ModuleUpdate<'a> {
+        id: &'a str,
+        factory: String,
+    }
+
+    #[derive(serde::Serialize)]
+    struct EcmascriptChunkUpdate<'a> {
+        added: Vec<ModuleUpdate<'a>>,
+        modified: Vec<ModuleUpdate<'a>>,
+        deleted: &'a [String],
+    }
+
+    async fn module_update(entry: &EcmascriptChunkEntry) -> Result<ModuleUpdate<'_>> {
+        let code = entry.code.await?;
+        let factory = String::from_utf8(code.source_code().to_bytes()?)?;
+        Ok(ModuleUpdate {
+            id: &entry.module_id,
+            factory,
+        })
+    }
+
+    let mut added_updates = Vec::with_capacity(added.len());
+    for entry in added {
+        added_updates.push(module_update(entry).await?);
+    }
+    let mut modified_updates = Vec::with_capacity(modified.len());
+    for entry in modified {
+        modified_updates.push(module_update(entry).await?);
+    }
+
+    let instruction = EcmascriptChunkUpdate {
+        added: added_updates,
+        modified: modified_updates,
+        deleted,
+    };
+
+    Ok(serde_json::to_string(&instruction)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_too_large_for_partial_update;
+
+    #[test]
+    fn small_changes_stay_partial() {
+        assert!(!is_too_large_for_partial_update(10, 1));
+        assert!(!is_too_large_for_partial_update(10, 5));
+    }
+
+    #[test]
+    fn majority_changed_falls_back_to_total() {
+        assert!(is_too_large_for_partial_update(10, 6));
+        assert!(is_too_large_for_partial_update(2, 2));
+    }
+
+    #[test]
+    fn empty_old_chunk_never_triggers_fallback() {
+        // There's nothing to diff against, `compute_update` handles this
+        // case before ever reaching the ratio check; the helper itself
+        // should still not divide by zero.
+        assert!(!is_too_large_for_partial_update(0, 5));
+    }
+}
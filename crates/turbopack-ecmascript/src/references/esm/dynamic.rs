@@ -55,7 +55,7 @@ impl AssetReference for EsmAsyncAssetReference {
     async fn description(&self) -> Result<StringVc> {
         Ok(StringVc::cell(format!(
             "dynamic import {}",
-            self.request.to_string().await?,
+            self.request.to_string().await?
         )))
     }
 }
@@ -0,0 +1,32 @@
+use turbo_tasks_fs::{FileContentRef, FileSystemPathRef};
+
+use crate::resolve::SpecialResolveResult;
+
+/// A single node of the module graph: something that has a path, some
+/// content, and references out to other assets.
+#[turbo_tasks::value_trait]
+pub trait Asset {
+    /// The path this asset is located at.
+    fn path(&self) -> FileSystemPathRef;
+    /// The content of this asset.
+    fn content(&self) -> FileContentRef;
+    /// Everything this asset's content references: other assets that are
+    /// part of the module graph, plus any special (non-module) results that
+    /// still need to be surfaced to downstream handling.
+    fn references(&self) -> AssetsSetRef;
+}
+
+/// Everything an [Asset] references, gathered while resolving its
+/// [crate::reference::AssetReference]s.
+///
+/// `specials` isn't just "the rest" after `assets` — externals, ignored
+/// modules, and raw files are real outcomes a bundler has to act on (e.g.
+/// an external has to be kept out of the chunk and referenced at runtime
+/// instead), so they're carried alongside `assets` rather than dropped once
+/// `for_each` has walked past them.
+#[turbo_tasks::value]
+#[derive(PartialEq, Eq, Default)]
+pub struct AssetsSet {
+    pub assets: Vec<AssetRef>,
+    pub specials: Vec<SpecialResolveResult>,
+}
@@ -1,10 +1,7 @@
 mod parse;
 mod references;
 
-use crate::{
-    asset::{Asset, AssetRef, AssetsSet, AssetsSetRef},
-    resolve::ResolveResult,
-};
+use crate::asset::{Asset, AssetRef, AssetsSet, AssetsSetRef};
 use anyhow::Result;
 use turbo_tasks_fs::{FileContentRef, FileSystemPathRef};
 
@@ -34,14 +31,32 @@ impl Asset for ModuleAsset {
     async fn references(&self) -> Result<AssetsSetRef> {
         let references_set = module_references(self.source.clone()).await?;
         let mut assets = Vec::new();
+        let mut specials = Vec::new();
         for reference in references_set.references.iter() {
             let resolve_result = reference
                 .clone()
                 .resolve(ModuleAssetRef::new(self.source.clone()).into());
-            if let ResolveResult::Module(module) = &*resolve_result.await? {
-                assets.push(module.clone());
-            }
+            // A single reference can resolve to several alternatives (e.g. a
+            // `browser`-field remap) and can mix module results with special
+            // ones (externals, ignored/empty modules, raw files). Fold every
+            // asset alternative into the graph instead of keeping only the
+            // first `Module` match; special results aren't modules, but
+            // they're not dropped either, they're surfaced through
+            // `specials` for downstream handling (chunking, externals) to
+            // act on.
+            resolve_result.await?.for_each(
+                |module| {
+                    if !assets.contains(module) {
+                        assets.push(module.clone());
+                    }
+                },
+                |special| {
+                    if !specials.contains(special) {
+                        specials.push(special.clone());
+                    }
+                },
+            );
         }
-        Ok(AssetsSet { assets }.into())
+        Ok(AssetsSet { assets, specials }.into())
     }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use turbo_tasks_fs::FileSystemPathRef;
+
+use crate::asset::AssetRef;
+
+/// The result of resolving a [crate::reference::AssetReference]'s request.
+///
+/// A single reference can legitimately resolve to more than one target at
+/// once: a `browser`-field remap maps a request to an alternative file
+/// alongside the original, a conditional export picks between several
+/// candidates, and an external falls outside the module graph entirely.
+/// `ResolveResult` keeps every alternative around instead of the caller
+/// having to pick one and silently drop the rest.
+#[derive(PartialEq, Eq, Clone)]
+pub enum ResolveResult {
+    /// The request resolved to an asset that's part of the module graph.
+    Module(AssetRef),
+    /// The request resolved to something that's not a module to keep
+    /// tracing into: an external package, an ignored/empty module, or a raw
+    /// (non-asset) file. Downstream handling (chunking, externals) decides
+    /// what to do with these; `references()` only needs to not drop them.
+    Special(SpecialResolveResult),
+    /// The request could not be resolved to anything.
+    Unresolveable,
+    /// The request resolved to more than one of the above at once (e.g. a
+    /// `browser`-field remap alongside the original target).
+    Alternatives(Vec<ResolveResult>),
+}
+
+/// A resolved result that isn't a module to keep tracing into.
+#[derive(PartialEq, Eq, Clone)]
+pub enum SpecialResolveResult {
+    /// Resolved to a package that's external to the bundle (e.g. a Node.js
+    /// builtin or a `package.json` `external` entry).
+    External(String),
+    /// Resolved to a request that should produce an empty/ignored module
+    /// (e.g. a `browser`-field `false` remap).
+    Ignored,
+    /// Resolved to a file that isn't itself an `Asset` (e.g. a raw file
+    /// handled outside the module graph).
+    Raw(FileSystemPathRef),
+}
+
+impl ResolveResult {
+    /// Walks `self` and every nested [ResolveResult::Alternatives],
+    /// invoking `visit_module` for each [ResolveResult::Module] and
+    /// `visit_special` for each [ResolveResult::Special] found.
+    pub fn for_each(
+        &self,
+        mut visit_module: impl FnMut(&AssetRef),
+        mut visit_special: impl FnMut(&SpecialResolveResult),
+    ) {
+        self.for_each_inner(&mut visit_module, &mut visit_special);
+    }
+
+    fn for_each_inner(
+        &self,
+        visit_module: &mut impl FnMut(&AssetRef),
+        visit_special: &mut impl FnMut(&SpecialResolveResult),
+    ) {
+        match self {
+            ResolveResult::Module(module) => visit_module(module),
+            ResolveResult::Special(special) => visit_special(special),
+            ResolveResult::Unresolveable => {}
+            ResolveResult::Alternatives(alternatives) => {
+                for alternative in alternatives {
+                    alternative.for_each_inner(visit_module, visit_special);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ResolveResult, SpecialResolveResult};
+
+    #[test]
+    fn for_each_walks_every_alternative() {
+        let result = ResolveResult::Alternatives(vec![
+            ResolveResult::Special(SpecialResolveResult::External("fs".to_string())),
+            ResolveResult::Unresolveable,
+            ResolveResult::Alternatives(vec![
+                ResolveResult::Special(SpecialResolveResult::Ignored),
+                ResolveResult::Special(SpecialResolveResult::External("fs".to_string())),
+            ]),
+        ]);
+
+        let mut specials = Vec::new();
+        result.for_each(|_module| unreachable!("no Module alternatives in this tree"), |special| {
+            specials.push(special.clone());
+        });
+
+        assert_eq!(
+            specials,
+            vec![
+                SpecialResolveResult::External("fs".to_string()),
+                SpecialResolveResult::Ignored,
+                SpecialResolveResult::External("fs".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn for_each_on_unresolveable_visits_nothing() {
+        let visited = std::cell::Cell::new(false);
+        ResolveResult::Unresolveable.for_each(
+            |_module| visited.set(true),
+            |_special| visited.set(true),
+        );
+        assert!(!visited.get());
+    }
+}